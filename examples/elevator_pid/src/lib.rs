@@ -3,6 +3,8 @@
 #[macro_use]
 extern crate serde_derive;
 
+use control_sim::integration;
+use control_sim::observer;
 use control_sim::units as si;
 use control_sim::util::clamp;
 use control_sim::*;
@@ -41,19 +43,82 @@ impl ElevatorPIDLoop {
         }
     }
 
-    pub fn acc(volt: si::Volt<f64>, vel: si::MeterPerSecond<f64>) -> si::MeterPerSecond2<f64> {
+    pub fn acc(
+        spec: &ElevatorSpec,
+        volt: si::Volt<f64>,
+        vel: si::MeterPerSecond<f64>,
+    ) -> si::MeterPerSecond2<f64> {
         #![allow(non_snake_case)]
-        let m = 5. * si::KG;
-        let r = 0.1524 * si::M;
-        let R = 12. * si::V / (133. * si::A);
-        let G = 20.; // how much slower the output is than input
-        let Kt = 24. * si::N * si::M / (133. * si::A);
-        let Kv = (558.15629415 /*rad*/ / si::S) / (12. * si::V);
+        let m = spec.mass;
+        let r = spec.pulley_radius;
+        let R = spec.armature_resistance;
+        let G = spec.gear_ratio;
+        let Kt = spec.kt;
+        let Kv = spec.kv;
 
         (G * Kt * (Kv * volt * r - G * vel)) / (m * Kv * R * r * r)
     }
 }
 
+/// Physical plant parameters for `ElevatorPIDLoop`'s `HarnessAble` impl:
+/// carriage mass and gearing, plus the simulation timesteps. Construct
+/// several of these (e.g. `ElevatorSpec::default().with_mass(...)`) to run
+/// the same controller against different plants.
+#[derive(Debug, Clone, Copy)]
+struct ElevatorSpec {
+    mass: si::Kilogram<f64>,
+    pulley_radius: si::Meter<f64>,
+    armature_resistance: units::Ohm<f64>,
+    gear_ratio: f64, // how much slower the output is than input
+    kt: units::NewtonMeterPerAmp<f64>,
+    kv: units::RadianPerSecondPerVolt<f64>,
+    simul_dt: si::Second<f64>,
+    control_dt: si::Second<f64>,
+}
+
+impl Default for ElevatorSpec {
+    fn default() -> Self {
+        Self {
+            mass: 5. * si::KG,
+            pulley_radius: 0.1524 * si::M,
+            armature_resistance: 12. * si::V / (133. * si::A),
+            gear_ratio: 20.,
+            kt: 24. * si::N * si::M / (133. * si::A),
+            kv: (558.15629415 /*rad*/ / si::S) / (12. * si::V),
+            // rk4 is accurate enough to take the whole control tick in one step
+            simul_dt: const_unit!(1. / 200.),
+            control_dt: const_unit!(1. / 200.),
+        }
+    }
+}
+
+impl ElevatorSpec {
+    fn with_mass(mut self, mass: si::Kilogram<f64>) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    fn with_gear_ratio(mut self, gear_ratio: f64) -> Self {
+        self.gear_ratio = gear_ratio;
+        self
+    }
+
+    fn with_control_dt(mut self, control_dt: si::Second<f64>) -> Self {
+        self.control_dt = control_dt;
+        self
+    }
+}
+
+impl SimSpec for ElevatorSpec {
+    fn simul_dt(&self) -> si::Second<f64> {
+        self.simul_dt
+    }
+
+    fn control_dt(&self) -> si::Second<f64> {
+        self.control_dt
+    }
+}
+
 impl ElevatorPIDLoop {
     fn iterate(&mut self, encoder: si::Meter<f64>, limit: bool) -> si::Volt<f64> {
         let filtered_goal;
@@ -120,19 +185,66 @@ impl HarnessAble for ElevatorPIDLoop {
     type State = ElevatorPhysicsState;
     type ControlResponse = si::Volt<f64>;
     type LogData = ElevatorLog;
-    // 1000 sims per dt
-    const SIMUL_DT: si::Second<f64> = const_unit!(1. / 200. / 1000.);
-    const CONTROL_DT: si::Second<f64> = const_unit!(1. / 200.);
-    fn sim_time(s: Self::State, r: Self::ControlResponse, dur: si::Second<f64>) -> Self::State {
-        let mut elapsed = 0. * si::S;
-        let mut pos = s.pos;
-        let mut vel = s.vel;
-        while elapsed < dur {
-            vel += ElevatorPIDLoop::acc(r, vel) * Self::SIMUL_DT;
-            pos += vel * Self::SIMUL_DT;
-            elapsed += Self::SIMUL_DT;
+    type Spec = ElevatorSpec;
+    fn sim_time(
+        spec: &ElevatorSpec,
+        s: Self::State,
+        r: Self::ControlResponse,
+        dur: si::Second<f64>,
+    ) -> Self::State {
+        let mps2 = si::MPS / si::S;
+        let mut y = PhaseVec {
+            pos: *(s.pos / si::M),
+            vel: *(s.vel / si::MPS),
+        };
+        // subdivide the control tick into simul_dt-sized physics substeps,
+        // so a spec with simul_dt < control_dt integrates more finely than
+        // the controller updates
+        let total = *(dur / si::S);
+        let steps = (total / *(spec.simul_dt() / si::S)).round().max(1.0) as usize;
+        let sub_dt = total / steps as f64;
+        for _ in 0..steps {
+            y = integration::rk4(
+                y,
+                |y: PhaseVec| PhaseVec {
+                    pos: y.vel,
+                    vel: *(ElevatorPIDLoop::acc(spec, r, y.vel * si::MPS) / mps2),
+                },
+                sub_dt,
+            );
+        }
+        ElevatorPhysicsState {
+            pos: y.pos * si::M,
+            vel: y.vel * si::MPS,
+        }
+    }
+}
+
+/// The bare (unit-stripped) phase vector `ElevatorPhysicsState` integrates
+/// over; see `integration::rk4`.
+#[derive(Debug, Clone, Copy)]
+struct PhaseVec {
+    pos: f64,
+    vel: f64,
+}
+
+impl std::ops::Add for PhaseVec {
+    type Output = PhaseVec;
+    fn add(self, rhs: PhaseVec) -> PhaseVec {
+        PhaseVec {
+            pos: self.pos + rhs.pos,
+            vel: self.vel + rhs.vel,
+        }
+    }
+}
+
+impl std::ops::Mul<f64> for PhaseVec {
+    type Output = PhaseVec;
+    fn mul(self, rhs: f64) -> PhaseVec {
+        PhaseVec {
+            pos: self.pos * rhs,
+            vel: self.vel * rhs,
         }
-        ElevatorPhysicsState { pos, vel }
     }
 }
 
@@ -189,12 +301,41 @@ impl StateShim<ElevatorPIDLoop> for ElevatorShim {
     }
 }
 
+/// Builds an `observer::ascii_observer` wired to this example's physical
+/// types: renders carriage position on the vertical scale and reports
+/// velocity, voltage, and the commanded setpoint (read off `ElevatorShim`,
+/// since the setpoint isn't part of `ElevatorPhysicsState`/`si::Volt<f64>`)
+/// alongside it.
+fn elevator_observer(
+    refresh: std::time::Duration,
+) -> observer::Observer<ElevatorPhysicsState, si::Volt<f64>, ElevatorShim> {
+    observer::ascii_observer(
+        *(ElevatorPIDLoop::MIN_HEIGHT / si::M),
+        *(ElevatorPIDLoop::MAX_HEIGHT / si::M),
+        refresh,
+        |_t, state: &ElevatorPhysicsState, response: &si::Volt<f64>, shim: &ElevatorShim| {
+            (
+                *(state.pos / si::M),
+                vec![
+                    ("vel", *(state.vel / si::MPS)),
+                    ("volts", *(*response / si::V)),
+                    ("sp", *(shim.controller().get_goal() / si::M)),
+                ],
+            )
+        },
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
     #[test]
     fn with_harness() {
         let mut harness = SimulationHarness::new(
+            ElevatorSpec::default(),
             ElevatorShim::new(1. * si::M, ElevatorPIDLoop::new()),
             ElevatorPhysicsState {
                 pos: 0.1 * si::M,
@@ -206,4 +347,54 @@ mod test {
         harness.shim_mut().controller_mut().set_goal(1. * si::M);
         harness.run_time(30. * si::S);
     }
+
+    #[test]
+    fn with_elevator_observer() {
+        let mut harness = SimulationHarness::new(
+            ElevatorSpec::default(),
+            ElevatorShim::new(1. * si::M, ElevatorPIDLoop::new()),
+            ElevatorPhysicsState {
+                pos: 0.1 * si::M,
+                vel: 0. * si::MPS,
+            },
+            20,
+        );
+        harness.shim_mut().controller_mut().set_goal(1. * si::M);
+        harness.with_observer(elevator_observer(std::time::Duration::from_secs(0)));
+        harness.run_time(1. * si::S);
+    }
+
+    #[test]
+    fn ascii_observer_draws_at_least_once() {
+        let calls = Rc::new(Cell::new(0usize));
+        let calls_in_observer = calls.clone();
+        let mut harness = SimulationHarness::new(
+            ElevatorSpec::default(),
+            ElevatorShim::new(1. * si::M, ElevatorPIDLoop::new()),
+            ElevatorPhysicsState {
+                pos: 0.1 * si::M,
+                vel: 0. * si::MPS,
+            },
+            20,
+        );
+        harness.shim_mut().controller_mut().set_goal(1. * si::M);
+        harness.with_observer(observer::ascii_observer(
+            *(ElevatorPIDLoop::MIN_HEIGHT / si::M),
+            *(ElevatorPIDLoop::MAX_HEIGHT / si::M),
+            std::time::Duration::from_secs(0),
+            move |_t, state: &ElevatorPhysicsState, response: &si::Volt<f64>, shim: &ElevatorShim| {
+                calls_in_observer.set(calls_in_observer.get() + 1);
+                (
+                    *(state.pos / si::M),
+                    vec![
+                        ("vel", *(state.vel / si::MPS)),
+                        ("volts", *(*response / si::V)),
+                        ("sp", *(shim.controller().get_goal() / si::M)),
+                    ],
+                )
+            },
+        ));
+        harness.run_time(1. * si::S);
+        assert!(calls.get() > 0);
+    }
 }
@@ -2,6 +2,9 @@
 #![feature(const_fn)]
 
 pub mod integration;
+pub mod observer;
+pub mod stats;
+pub mod sweep;
 
 // re-exports
 pub mod approx {
@@ -13,10 +16,16 @@ pub mod units {
     pub use dimensioned::si::*;
     use dimensioned::{
         si,
-        typenum::{tarr, N1, N2, P1, Z0},
+        typenum::{tarr, N1, N2, N3, P1, P2, Z0},
     };
     /// Used for Kd in PID loops
     pub type VoltSecondPerMeter<V> = si::SI<V, tarr![P1, P1, N2, N1, Z0, Z0, Z0]>; // also Newtons per Amp
+    /// Motor resistance (V/A); used in plant specs
+    pub type Ohm<V> = si::SI<V, tarr![P2, P1, N3, N2, Z0, Z0, Z0]>;
+    /// Motor torque constant (N*m/A); used in plant specs
+    pub type NewtonMeterPerAmp<V> = si::SI<V, tarr![P2, P1, N2, N1, Z0, Z0, Z0]>;
+    /// Motor speed constant (rad/s per V); used in plant specs
+    pub type RadianPerSecondPerVolt<V> = si::SI<V, tarr![N2, N1, P2, P1, Z0, Z0, Z0]>;
 
     #[macro_export]
     macro_rules! const_unit {
@@ -40,14 +49,31 @@ pub trait HarnessAble {
     type ControlResponse: Copy;
     /// A type holding all the data logged to a csv once the test completes
     type LogData: Serialize;
+    /// Plant parameters (mass, gearing, timesteps, ...) needed to simulate
+    /// physics, so the same controller can be run against several physical
+    /// plants without editing source and recompiling.
+    type Spec: SimSpec;
     /// Physically simulates the system over the time where the control response is constant
-    fn sim_time(s: Self::State, r: Self::ControlResponse, dur: si::Second<f64>) -> Self::State;
+    fn sim_time(
+        spec: &Self::Spec,
+        s: Self::State,
+        r: Self::ControlResponse,
+        dur: si::Second<f64>,
+    ) -> Self::State;
+}
+
+/// The timesteps every `HarnessAble::Spec` must provide.
+pub trait SimSpec {
     /// The duration of one period for physics simulation
-    const SIMUL_DT: si::Second<f64>;
+    fn simul_dt(&self) -> si::Second<f64>;
     /// The interval between control response updates
-    const CONTROL_DT: si::Second<f64>;
+    fn control_dt(&self) -> si::Second<f64>;
 }
 
+/// A one-shot command fired against a `StateShim` by `run_schedule` once the
+/// run crosses its scheduled timestamp.
+pub type ScheduledCommand<SHIM> = Box<dyn FnOnce(&mut SHIM)>;
+
 /// Shims a physically simulated state into simulated sensors passed to the control loop.
 ///
 /// Can be used to simulate things like encoder offsets, failing sensors.
@@ -75,12 +101,14 @@ where
     SYS: HarnessAble,
     SHIM: StateShim<SYS>,
 {
+    spec: SYS::Spec,
     shim: SHIM,
     state: SYS::State,
     time: si::Second<f64>,
     log_every: u32,
     csv: Option<csv::Writer<File>>,
     log: Vec<SYS::LogData>,
+    observer: Option<observer::Observer<SYS::State, SYS::ControlResponse, SHIM>>,
 }
 
 impl<SYS, SHIM> SimulationHarness<SYS, SHIM>
@@ -88,17 +116,35 @@ where
     SYS: HarnessAble,
     SHIM: StateShim<SYS>,
 {
-    pub fn new(shim: SHIM, initial: SYS::State, log_every: u32) -> Self {
+    pub fn new(spec: SYS::Spec, shim: SHIM, initial: SYS::State, log_every: u32) -> Self {
         Self {
+            spec,
             shim,
             state: initial,
             time: 0. * si::S,
             log_every,
             csv: None,
             log: Vec::new(),
+            observer: None,
         }
     }
 
+    pub fn spec(&self) -> &SYS::Spec {
+        &self.spec
+    }
+
+    pub fn spec_mut(&mut self) -> &mut SYS::Spec {
+        &mut self.spec
+    }
+
+    /// Registers a real-time observer, invoked at the same cadence as
+    /// logging so a run can be watched qualitatively (oscillation,
+    /// saturation) as it progresses. See `observer::ascii_observer` for a
+    /// built-in renderer.
+    pub fn with_observer(&mut self, obs: observer::Observer<SYS::State, SYS::ControlResponse, SHIM>) {
+        self.observer = Some(obs);
+    }
+
     pub fn use_csv<P: AsRef<Path> + std::fmt::Debug>(&mut self, path: P) {
         match path.as_ref().parent() {
             Some(dir) => std::fs::create_dir_all(dir).expect(&format!(
@@ -124,17 +170,63 @@ where
         &mut self.shim
     }
 
+    /// Computes per-field statistics (mean, standard deviation, min, max,
+    /// final value) over every numeric field of the accumulated log, so a
+    /// run can be sanity-checked (e.g. RMS tracking error, peak voltage)
+    /// without exporting the csv and loading it elsewhere.
+    pub fn summary(&self) -> Vec<(String, stats::Stats)> {
+        stats::summarize(&self.log)
+    }
+
+    /// Writes `summary()` to stdout as a formatted table.
+    pub fn print_summary(&self) {
+        stats::print_summary(&self.log)
+    }
+
     pub fn run_time(&mut self, time: si::Second<f64>) -> SYS::State {
+        self.run_schedule(time, Vec::new())
+    }
+
+    /// Like `run_time`, but also fires scheduled commands against the shim
+    /// as the run crosses their timestamps — e.g. `set_goal` calls for a
+    /// sequence of waypoints — rather than requiring a single goal to be
+    /// set up front. `events` need not be sorted; each callback fires
+    /// exactly once, at the first control tick at or after its scheduled
+    /// time. Callbacks whose time is not reached before this run ends are
+    /// dropped without firing. Logging and assertions behave exactly as in
+    /// `run_time`.
+    pub fn run_schedule(
+        &mut self,
+        time: si::Second<f64>,
+        mut events: Vec<(si::Second<f64>, ScheduledCommand<SHIM>)>,
+    ) -> SYS::State {
+        events.sort_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .expect("event scheduled at a NaN time")
+        });
+        let mut events = events.into_iter().peekable();
+
         let mut elapsed = 0. * si::S;
         let mut count = 0;
         while elapsed < time {
+            while let Some((t, _)) = events.peek() {
+                if *t > self.time {
+                    break;
+                }
+                let (_, command) = events.next().unwrap();
+                command(&mut self.shim);
+            }
+            let control_dt = self.spec.control_dt();
             let response = self.shim.update(self.state);
-            self.state = SYS::sim_time(self.state, response, SYS::CONTROL_DT);
+            self.state = SYS::sim_time(&self.spec, self.state, response, control_dt);
             self.shim.assert(self.state);
-            elapsed += SYS::CONTROL_DT;
-            self.time += SYS::CONTROL_DT;
+            elapsed += control_dt;
+            self.time += control_dt;
             count += 1;
             if count >= self.log_every {
+                if let Some(ref mut obs) = self.observer {
+                    obs(self.time, &self.state, &response, &self.shim);
+                }
                 self.log
                     .push(self.shim.log_dat(self.state, response, self.time));
                 count = 0;
@@ -164,7 +256,9 @@ where
 use self::units as si;
 
 pub trait SimulationLaw<V> {
-    fn acc(volt: si::Volt<V>, vel: si::MeterPerSecond<V>) -> si::MeterPerSecond2<V>;
+    /// Plant parameters this law computes acceleration from
+    type Spec;
+    fn acc(spec: &Self::Spec, volt: si::Volt<V>, vel: si::MeterPerSecond<V>) -> si::MeterPerSecond2<V>;
 }
 
 #[macro_use]
@@ -186,6 +280,90 @@ mod assertions;
 #[macro_use]
 extern crate serde_derive;
 
+/// Tests `SimulationHarness::run_schedule`'s event-ordering contract against
+/// a minimal toy `HarnessAble`/`StateShim`, independent of the elevator
+/// domain `mod example` exercises elsewhere.
+#[cfg(test)]
+mod schedule_test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, Copy)]
+    struct ToySpec;
+
+    impl SimSpec for ToySpec {
+        fn simul_dt(&self) -> si::Second<f64> {
+            1.0 * si::S
+        }
+        fn control_dt(&self) -> si::Second<f64> {
+            1.0 * si::S
+        }
+    }
+
+    struct ToySys;
+
+    #[derive(Debug, Clone, Copy, Serialize)]
+    struct ToyLog {
+        x: f64,
+    }
+
+    impl HarnessAble for ToySys {
+        type State = f64;
+        type ControlResponse = ();
+        type LogData = ToyLog;
+        type Spec = ToySpec;
+        fn sim_time(_spec: &ToySpec, s: f64, _r: (), dur: si::Second<f64>) -> f64 {
+            s + *(dur / si::S)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct ToyShim {
+        fired: Rc<RefCell<Vec<(f64, &'static str)>>>,
+    }
+
+    impl StateShim<ToySys> for ToyShim {
+        fn update(&mut self, _state: f64) {}
+
+        fn log_dat(&mut self, s: f64, _r: (), _t: si::Second<f64>) -> ToyLog {
+            ToyLog { x: s }
+        }
+    }
+
+    #[test]
+    fn run_schedule_fires_out_of_order_events_in_time_order() {
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let mut harness = SimulationHarness::new(
+            ToySpec,
+            ToyShim {
+                fired: fired.clone(),
+            },
+            0.0,
+            1,
+        );
+
+        let (f1, f2, f3) = (fired.clone(), fired.clone(), fired.clone());
+        let events: Vec<(si::Second<f64>, ScheduledCommand<ToyShim>)> = vec![
+            (
+                3.0 * si::S,
+                Box::new(move |_: &mut ToyShim| f3.borrow_mut().push((3.0, "c"))),
+            ),
+            (
+                1.0 * si::S,
+                Box::new(move |_: &mut ToyShim| f1.borrow_mut().push((1.0, "a"))),
+            ),
+            (
+                2.0 * si::S,
+                Box::new(move |_: &mut ToyShim| f2.borrow_mut().push((2.0, "b"))),
+            ),
+        ];
+        harness.run_schedule(5.0 * si::S, events);
+
+        assert_eq!(*fired.borrow(), vec![(1.0, "a"), (2.0, "b"), (3.0, "c")]);
+    }
+}
+
 mod example {
     use super::*;
     #[derive(Copy, Clone, Debug)]
@@ -222,19 +400,82 @@ mod example {
             }
         }
 
-        pub fn acc(volt: si::Volt<f64>, vel: si::MeterPerSecond<f64>) -> si::MeterPerSecond2<f64> {
+        pub fn acc(
+            spec: &ElevatorSpec,
+            volt: si::Volt<f64>,
+            vel: si::MeterPerSecond<f64>,
+        ) -> si::MeterPerSecond2<f64> {
             #![allow(non_snake_case)]
-            let m = 5. * si::KG;
-            let r = 0.1524 * si::M;
-            let R = 12. * si::V / (133. * si::A);
-            let G = 20.; // how much slower the output is than input
-            let Kt = 24. * si::N * si::M / (133. * si::A);
-            let Kv = (558.15629415 /*rad*/ / si::S) / (12. * si::V);
+            let m = spec.mass;
+            let r = spec.pulley_radius;
+            let R = spec.armature_resistance;
+            let G = spec.gear_ratio;
+            let Kt = spec.kt;
+            let Kv = spec.kv;
 
             (G * Kt * (Kv * volt * r - G * vel)) / (m * Kv * R * r * r)
         }
     }
 
+    /// Physical plant parameters for `ElevatorPIDLoop`'s `HarnessAble` impl:
+    /// carriage mass and gearing, plus the simulation timesteps. Construct
+    /// several of these (e.g. `ElevatorSpec::default().with_mass(...)`) to
+    /// run the same controller against different plants.
+    #[derive(Debug, Clone, Copy)]
+    struct ElevatorSpec {
+        mass: si::Kilogram<f64>,
+        pulley_radius: si::Meter<f64>,
+        armature_resistance: units::Ohm<f64>,
+        gear_ratio: f64, // how much slower the output is than input
+        kt: units::NewtonMeterPerAmp<f64>,
+        kv: units::RadianPerSecondPerVolt<f64>,
+        simul_dt: si::Second<f64>,
+        control_dt: si::Second<f64>,
+    }
+
+    impl Default for ElevatorSpec {
+        fn default() -> Self {
+            Self {
+                mass: 5. * si::KG,
+                pulley_radius: 0.1524 * si::M,
+                armature_resistance: 12. * si::V / (133. * si::A),
+                gear_ratio: 20.,
+                kt: 24. * si::N * si::M / (133. * si::A),
+                kv: (558.15629415 /*rad*/ / si::S) / (12. * si::V),
+                // rk4 is accurate enough to take the whole control tick in one step
+                simul_dt: const_unit!(1. / 200.),
+                control_dt: const_unit!(1. / 200.),
+            }
+        }
+    }
+
+    impl ElevatorSpec {
+        fn with_mass(mut self, mass: si::Kilogram<f64>) -> Self {
+            self.mass = mass;
+            self
+        }
+
+        fn with_gear_ratio(mut self, gear_ratio: f64) -> Self {
+            self.gear_ratio = gear_ratio;
+            self
+        }
+
+        fn with_control_dt(mut self, control_dt: si::Second<f64>) -> Self {
+            self.control_dt = control_dt;
+            self
+        }
+    }
+
+    impl SimSpec for ElevatorSpec {
+        fn simul_dt(&self) -> si::Second<f64> {
+            self.simul_dt
+        }
+
+        fn control_dt(&self) -> si::Second<f64> {
+            self.control_dt
+        }
+    }
+
     impl ElevatorPIDLoop {
         fn iterate(&mut self, encoder: si::Meter<f64>, limit: bool) -> si::Volt<f64> {
             let filtered_goal;
@@ -301,19 +542,66 @@ mod example {
         type State = ElevatorPhysicsState;
         type ControlResponse = si::Volt<f64>;
         type LogData = ElevatorLog;
-        // 1000 sims per dt
-        const SIMUL_DT: si::Second<f64> = const_unit!(1. / 200. / 1000.);
-        const CONTROL_DT: si::Second<f64> = const_unit!(1. / 200.);
-        fn sim_time(s: Self::State, r: Self::ControlResponse, dur: si::Second<f64>) -> Self::State {
-            let mut elapsed = 0. * si::S;
-            let mut pos = s.pos;
-            let mut vel = s.vel;
-            while elapsed < dur {
-                vel += ElevatorPIDLoop::acc(r, vel) * Self::SIMUL_DT;
-                pos += vel * Self::SIMUL_DT;
-                elapsed += Self::SIMUL_DT;
+        type Spec = ElevatorSpec;
+        fn sim_time(
+            spec: &ElevatorSpec,
+            s: Self::State,
+            r: Self::ControlResponse,
+            dur: si::Second<f64>,
+        ) -> Self::State {
+            let mps2 = si::MPS / si::S;
+            let mut y = PhaseVec {
+                pos: *(s.pos / si::M),
+                vel: *(s.vel / si::MPS),
+            };
+            // subdivide the control tick into simul_dt-sized physics
+            // substeps, so a spec with simul_dt < control_dt integrates
+            // more finely than the controller updates
+            let total = *(dur / si::S);
+            let steps = (total / *(spec.simul_dt() / si::S)).round().max(1.0) as usize;
+            let sub_dt = total / steps as f64;
+            for _ in 0..steps {
+                y = integration::rk4(
+                    y,
+                    |y: PhaseVec| PhaseVec {
+                        pos: y.vel,
+                        vel: *(ElevatorPIDLoop::acc(spec, r, y.vel * si::MPS) / mps2),
+                    },
+                    sub_dt,
+                );
+            }
+            ElevatorPhysicsState {
+                pos: y.pos * si::M,
+                vel: y.vel * si::MPS,
+            }
+        }
+    }
+
+    /// The bare (unit-stripped) phase vector `ElevatorPhysicsState` integrates
+    /// over; see `integration::rk4`.
+    #[derive(Debug, Clone, Copy)]
+    struct PhaseVec {
+        pos: f64,
+        vel: f64,
+    }
+
+    impl std::ops::Add for PhaseVec {
+        type Output = PhaseVec;
+        fn add(self, rhs: PhaseVec) -> PhaseVec {
+            PhaseVec {
+                pos: self.pos + rhs.pos,
+                vel: self.vel + rhs.vel,
+            }
+        }
+    }
+
+    impl std::ops::Mul<f64> for PhaseVec {
+        type Output = PhaseVec;
+        fn mul(self, rhs: f64) -> PhaseVec {
+            PhaseVec {
+                pos: self.pos * rhs,
+                vel: self.vel * rhs,
             }
-            ElevatorPhysicsState { pos, vel }
         }
     }
 
@@ -370,12 +658,41 @@ mod example {
         }
     }
 
+    /// Builds an `observer::ascii_observer` wired to this example's physical
+    /// types: renders carriage position on the vertical scale and reports
+    /// velocity, voltage, and the commanded setpoint (read off
+    /// `ElevatorShim`, since the setpoint isn't part of
+    /// `ElevatorPhysicsState`/`si::Volt<f64>`) alongside it.
+    fn elevator_observer(
+        refresh: std::time::Duration,
+    ) -> observer::Observer<ElevatorPhysicsState, si::Volt<f64>, ElevatorShim> {
+        observer::ascii_observer(
+            *(ElevatorPIDLoop::MIN_HEIGHT / si::M),
+            *(ElevatorPIDLoop::MAX_HEIGHT / si::M),
+            refresh,
+            |_t, state: &ElevatorPhysicsState, response: &si::Volt<f64>, shim: &ElevatorShim| {
+                (
+                    *(state.pos / si::M),
+                    vec![
+                        ("vel", *(state.vel / si::MPS)),
+                        ("volts", *(*response / si::V)),
+                        ("sp", *(shim.controller().get_goal() / si::M)),
+                    ],
+                )
+            },
+        )
+    }
+
     #[cfg(test)]
     mod test {
         use super::*;
+        use std::cell::Cell;
+        use std::rc::Rc;
+
         #[test]
         fn with_harness() {
             let mut harness = SimulationHarness::new(
+                ElevatorSpec::default(),
                 ElevatorShim::new(1. * si::M, ElevatorPIDLoop::new()),
                 ElevatorPhysicsState {
                     pos: 0.1 * si::M,
@@ -387,5 +704,58 @@ mod example {
             harness.shim_mut().controller_mut().set_goal(1. * si::M);
             harness.run_time(30. * si::S);
         }
+
+        #[test]
+        fn with_elevator_observer() {
+            let mut harness = SimulationHarness::new(
+                ElevatorSpec::default(),
+                ElevatorShim::new(1. * si::M, ElevatorPIDLoop::new()),
+                ElevatorPhysicsState {
+                    pos: 0.1 * si::M,
+                    vel: 0. * si::MPS,
+                },
+                20,
+            );
+            harness.shim_mut().controller_mut().set_goal(1. * si::M);
+            harness.with_observer(elevator_observer(std::time::Duration::from_secs(0)));
+            harness.run_time(1. * si::S);
+        }
+
+        #[test]
+        fn ascii_observer_draws_at_least_once() {
+            let calls = Rc::new(Cell::new(0usize));
+            let calls_in_observer = calls.clone();
+            let mut harness = SimulationHarness::new(
+                ElevatorSpec::default(),
+                ElevatorShim::new(1. * si::M, ElevatorPIDLoop::new()),
+                ElevatorPhysicsState {
+                    pos: 0.1 * si::M,
+                    vel: 0. * si::MPS,
+                },
+                20,
+            );
+            harness.shim_mut().controller_mut().set_goal(1. * si::M);
+            harness.with_observer(observer::ascii_observer(
+                *(ElevatorPIDLoop::MIN_HEIGHT / si::M),
+                *(ElevatorPIDLoop::MAX_HEIGHT / si::M),
+                std::time::Duration::from_secs(0),
+                move |_t,
+                      state: &ElevatorPhysicsState,
+                      response: &si::Volt<f64>,
+                      shim: &ElevatorShim| {
+                    calls_in_observer.set(calls_in_observer.get() + 1);
+                    (
+                        *(state.pos / si::M),
+                        vec![
+                            ("vel", *(state.vel / si::MPS)),
+                            ("volts", *(*response / si::V)),
+                            ("sp", *(shim.controller().get_goal() / si::M)),
+                        ],
+                    )
+                },
+            ));
+            harness.run_time(1. * si::S);
+            assert!(calls.get() > 0);
+        }
     }
 }
@@ -0,0 +1,67 @@
+//! A live terminal observer for `SimulationHarness::with_observer`.
+//!
+//! `ascii_observer` redraws a vertical-scale marker plus a handful of
+//! labeled extra readouts each time it's invoked, throttled to a wall-clock
+//! refresh rate so fast sims don't flood the terminal.
+
+use crate::units as si;
+use crate::util::clamp;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const ROWS: usize = 20;
+
+/// Boxed observer callback, as stored by `SimulationHarness`: fired with the
+/// current time, physical state, control response, and the `StateShim`
+/// driving the run (e.g. to read back a setpoint the shim holds but the
+/// state/response don't carry).
+pub type Observer<S, R, SHIM> = Box<dyn FnMut(si::Second<f64>, &S, &R, &SHIM)>;
+
+/// Builds an observer for `SimulationHarness::with_observer` that renders
+/// `extract`'s projection of the state as a marker moving between `min`
+/// and `max` on a vertical scale, alongside whatever extra `(label, value)`
+/// pairs `extract` returns (e.g. velocity, voltage, setpoint). A redraw is
+/// skipped unless at least `refresh` wall-clock time has passed since the
+/// last one.
+pub fn ascii_observer<S, R, SHIM>(
+    min: f64,
+    max: f64,
+    refresh: Duration,
+    mut extract: impl FnMut(si::Second<f64>, &S, &R, &SHIM) -> (f64, Vec<(&'static str, f64)>)
+        + 'static,
+) -> Observer<S, R, SHIM> {
+    let mut last_drawn = None::<Instant>;
+    Box::new(move |t, state, response, shim| {
+        let now = Instant::now();
+        if let Some(last) = last_drawn {
+            if now.duration_since(last) < refresh {
+                return;
+            }
+        }
+        last_drawn = Some(now);
+
+        let (value, extras) = extract(t, state, response, shim);
+        draw(t, min, max, value, &extras);
+    })
+}
+
+fn draw(t: si::Second<f64>, min: f64, max: f64, value: f64, extras: &[(&'static str, f64)]) {
+    let marker_row = clamp(
+        ((value - min) / (max - min) * (ROWS as f64 - 1.0)).round() as isize,
+        0,
+        ROWS as isize - 1,
+    );
+
+    let mut out = std::io::stdout();
+    // clear the screen and move the cursor home, like a raw-terminal redraw
+    let _ = write!(out, "\x1B[2J\x1B[H");
+    let _ = writeln!(out, "t = {:.3}s", *(t / si::S));
+    for row in (0..ROWS).rev() {
+        let marker = if row as isize == marker_row { "*" } else { "" };
+        let _ = writeln!(out, "|{}", marker);
+    }
+    for (label, v) in extras {
+        let _ = writeln!(out, "{:>8}: {:.4}", label, v);
+    }
+    let _ = out.flush();
+}
@@ -0,0 +1,490 @@
+//! Post-run statistical summaries over a `SimulationHarness`'s logged data.
+//!
+//! `SYS::LogData` is an arbitrary `Serialize` struct chosen by each
+//! `StateShim` impl, so field names aren't known here at compile time.
+//! Instead each record is pushed through `FieldCollector`, a small
+//! `serde::Serializer` that walks a flat struct and keeps only its
+//! `f64`-representable leaves, keyed by field name. Those leaves are folded
+//! with Welford's online algorithm so the whole log only needs one pass.
+
+use serde::{ser, Serialize};
+use std::fmt;
+
+/// Per-field summary statistics: mean, standard deviation, min, max, and the
+/// final logged value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub last: f64,
+}
+
+/// Computes `Stats` for every numeric field across `records`, in one pass.
+pub fn summarize<T: Serialize>(records: &[T]) -> Vec<(String, Stats)> {
+    let mut acc: Vec<(String, Welford)> = Vec::new();
+    for record in records {
+        for (name, value) in collect_fields(record) {
+            match acc.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, w)) => w.push(value),
+                None => acc.push((name, Welford::new(value))),
+            }
+        }
+    }
+    acc.into_iter().map(|(name, w)| (name, w.finish())).collect()
+}
+
+/// Writes `summarize(records)` to stdout as a formatted table: one row per
+/// field, columns for mean/std dev/min/max/final value.
+pub fn print_summary<T: Serialize>(records: &[T]) {
+    let stats = summarize(records);
+    let name_width = stats.iter().map(|(n, _)| n.len()).max().unwrap_or(0).max(5);
+    println!(
+        "{:width$}  {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "field",
+        "mean",
+        "std dev",
+        "min",
+        "max",
+        "final",
+        width = name_width
+    );
+    for (name, s) in &stats {
+        println!(
+            "{:width$}  {:>12.6} {:>12.6} {:>12.6} {:>12.6} {:>12.6}",
+            name, s.mean, s.std_dev, s.min, s.max, s.last, width = name_width
+        );
+    }
+}
+
+/// Welford's online mean/variance accumulator, plus running min/max/last.
+///
+/// count n, mean mu += (x-mu)/n, m2 += (x-mu_old)(x-mu_new),
+/// variance = m2/n
+#[derive(Debug, Clone, Copy)]
+struct Welford {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+    last: f64,
+}
+
+impl Welford {
+    fn new(x: f64) -> Self {
+        Welford {
+            count: 1,
+            mean: x,
+            m2: 0.0,
+            min: x,
+            max: x,
+            last: x,
+        }
+    }
+
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        let delta2 = x - self.mean;
+        self.m2 += delta * delta2;
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        self.last = x;
+    }
+
+    fn finish(&self) -> Stats {
+        Stats {
+            count: self.count,
+            mean: self.mean,
+            std_dev: (self.m2 / self.count as f64).sqrt(),
+            min: self.min,
+            max: self.max,
+            last: self.last,
+        }
+    }
+}
+
+/// Serializes `record` and collects the `f64`-representable leaves of its
+/// fields, keyed by field name. `LogData` structs in this crate are flat
+/// records of numeric fields (see `ElevatorLog`), so this only supports one
+/// level of struct nesting; anything else is a serialization error.
+fn collect_fields<T: Serialize>(record: &T) -> Vec<(String, f64)> {
+    let mut fields = Vec::new();
+    record
+        .serialize(FieldCollector {
+            fields: &mut fields,
+        })
+        .expect("LogData must serialize as a flat struct of numeric fields");
+    fields
+}
+
+#[derive(Debug)]
+struct CollectError(String);
+
+impl fmt::Display for CollectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CollectError {}
+
+impl ser::Error for CollectError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        CollectError(msg.to_string())
+    }
+}
+
+macro_rules! unsupported_leaf {
+    () => {
+        Err(CollectError(
+            "expected a flat struct of numeric fields".to_string(),
+        ))
+    };
+}
+
+struct FieldCollector<'a> {
+    fields: &'a mut Vec<(String, f64)>,
+}
+
+impl<'a> ser::Serializer for FieldCollector<'a> {
+    type Ok = ();
+    type Error = CollectError;
+    type SerializeSeq = ser::Impossible<(), CollectError>;
+    type SerializeTuple = ser::Impossible<(), CollectError>;
+    type SerializeTupleStruct = ser::Impossible<(), CollectError>;
+    type SerializeTupleVariant = ser::Impossible<(), CollectError>;
+    type SerializeMap = ser::Impossible<(), CollectError>;
+    type SerializeStruct = StructCollector<'a>;
+    type SerializeStructVariant = ser::Impossible<(), CollectError>;
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(StructCollector {
+            fields: self.fields,
+        })
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        unsupported_leaf!()
+    }
+}
+
+struct StructCollector<'a> {
+    fields: &'a mut Vec<(String, f64)>,
+}
+
+impl<'a> ser::SerializeStruct for StructCollector<'a> {
+    type Ok = ();
+    type Error = CollectError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        if let Some(x) = value.serialize(LeafSerializer)? {
+            self.fields.push((key.to_string(), x));
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+/// Extracts a single `f64`-representable leaf value, or `None` if the value
+/// is a non-numeric scalar (e.g. a bool, string or unit/newtype enum field,
+/// which `LogData` structs in this crate don't use but which shouldn't blow
+/// up the whole summary). Sequences, tuples, maps, nested structs and
+/// tuple/struct enum variants are a serialization error.
+struct LeafSerializer;
+
+impl ser::Serializer for LeafSerializer {
+    type Ok = Option<f64>;
+    type Error = CollectError;
+    type SerializeSeq = ser::Impossible<Option<f64>, CollectError>;
+    type SerializeTuple = ser::Impossible<Option<f64>, CollectError>;
+    type SerializeTupleStruct = ser::Impossible<Option<f64>, CollectError>;
+    type SerializeTupleVariant = ser::Impossible<Option<f64>, CollectError>;
+    type SerializeMap = ser::Impossible<Option<f64>, CollectError>;
+    type SerializeStruct = ser::Impossible<Option<f64>, CollectError>;
+    type SerializeStructVariant = ser::Impossible<Option<f64>, CollectError>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v as f64))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(v))
+    }
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        unsupported_leaf!()
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        unsupported_leaf!()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, Serialize)]
+    struct Rec {
+        x: f64,
+        // non-numeric fields should be skipped, not cause a panic
+        label: &'static str,
+    }
+
+    #[test]
+    fn summarize_matches_closed_form() {
+        let records = [
+            Rec { x: 1.0, label: "a" },
+            Rec { x: 2.0, label: "b" },
+            Rec { x: 3.0, label: "c" },
+        ];
+        let stats = summarize(&records);
+        assert_eq!(stats.len(), 1);
+        let (name, s) = &stats[0];
+        assert_eq!(name, "x");
+        assert_eq!(s.count, 3);
+        assert!((s.mean - 2.0).abs() < 1e-12);
+        assert!((s.std_dev - (2.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(s.min, 1.0);
+        assert_eq!(s.max, 3.0);
+        assert_eq!(s.last, 3.0);
+    }
+
+    #[test]
+    fn collect_fields_skips_non_numeric_fields_without_panicking() {
+        let fields = collect_fields(&Rec {
+            x: 5.0,
+            label: "ignored",
+        });
+        assert_eq!(fields, vec![("x".to_string(), 5.0)]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Batch / Monte-Carlo sweep driver.
+//!
+//! Builds on the runtime `SimSpec` every `HarnessAble::Spec` implements and
+//! the shim configuration a `StateShim` impl already carries (e.g.
+//! `enc_off` on `ElevatorShim`): `run_sweep` takes a batch of independent
+//! `(spec, initial_state, shim)` trials, runs each to completion via a
+//! fresh `SimulationHarness`, and aggregates their `summary()` statistics.
+//! Trials run in parallel (rayon) since each harness is independent, and a
+//! trial whose `StateShim::assert` panics is collected as a failed
+//! `TrialResult` rather than aborting the whole sweep.
+
+use crate::units as si;
+use crate::{stats, HarnessAble, SimulationHarness, StateShim};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// The outcome of one trial: its per-field statistics, or the panic message
+/// from a failed `StateShim::assert` if the trial didn't run to completion.
+#[derive(Debug, Clone)]
+pub struct TrialResult {
+    pub stats: Vec<(String, stats::Stats)>,
+    pub failure: Option<String>,
+}
+
+/// The aggregated result of a sweep.
+#[derive(Debug, Clone)]
+pub struct SweepReport {
+    pub trials: Vec<TrialResult>,
+}
+
+impl SweepReport {
+    /// Fraction of trials whose `StateShim::assert` panicked.
+    pub fn failure_rate(&self) -> f64 {
+        if self.trials.is_empty() {
+            return 0.0;
+        }
+        let failed = self.trials.iter().filter(|t| t.failure.is_some()).count();
+        failed as f64 / self.trials.len() as f64
+    }
+
+    /// The `(min, max)` of `field` taken across every trial that ran to
+    /// completion, e.g. to characterize worst-case overshoot across a
+    /// sweep. `None` if no completed trial logged that field.
+    pub fn field_extremes(&self, field: &str) -> Option<(f64, f64)> {
+        let mut lo = f64::INFINITY;
+        let mut hi = f64::NEG_INFINITY;
+        let mut any = false;
+        for t in &self.trials {
+            if let Some((_, s)) = t.stats.iter().find(|(name, _)| name == field) {
+                lo = lo.min(s.min);
+                hi = hi.max(s.max);
+                any = true;
+            }
+        }
+        if any {
+            Some((lo, hi))
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs every trial in `trials` to completion over `duration`, in parallel.
+///
+/// If `csv_dir` is set, trial `i`'s log is additionally written to
+/// `csv_dir/trial_<i>.csv` (the directory is created up front, reusing
+/// `use_csv`'s parent-directory-creation behavior).
+pub fn run_sweep<SYS, SHIM>(
+    trials: Vec<(SYS::Spec, SYS::State, SHIM)>,
+    duration: si::Second<f64>,
+    csv_dir: Option<&Path>,
+) -> SweepReport
+where
+    SYS: HarnessAble,
+    SYS::Spec: Send,
+    SYS::State: Send,
+    SHIM: StateShim<SYS> + Send,
+{
+    if let Some(dir) = csv_dir {
+        std::fs::create_dir_all(dir)
+            .unwrap_or_else(|_| panic!("Could not create sweep csv directory {:?}", dir));
+    }
+
+    let results = trials
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, (spec, initial, shim))| run_trial::<SYS, SHIM>(i, spec, initial, shim, duration, csv_dir))
+        .collect();
+
+    SweepReport { trials: results }
+}
+
+fn run_trial<SYS, SHIM>(
+    index: usize,
+    spec: SYS::Spec,
+    initial: SYS::State,
+    shim: SHIM,
+    duration: si::Second<f64>,
+    csv_dir: Option<&Path>,
+) -> TrialResult
+where
+    SYS: HarnessAble,
+    SHIM: StateShim<SYS>,
+{
+    let csv_path: Option<PathBuf> = csv_dir.map(|dir| dir.join(format!("trial_{}.csv", index)));
+
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        let mut harness = SimulationHarness::<SYS, SHIM>::new(spec, shim, initial, 1);
+        if let Some(path) = csv_path {
+            harness.use_csv(path);
+        }
+        harness.run_time(duration);
+        harness.summary()
+    }));
+
+    match outcome {
+        Ok(stats) => TrialResult {
+            stats,
+            failure: None,
+        },
+        Err(payload) => TrialResult {
+            stats: Vec::new(),
+            failure: Some(panic_message(payload)),
+        },
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "trial panicked with a non-string payload".to_string()
+    }
+}
+
+/// Builds `n` trials by sampling a `StdRng` seeded with `seed` (so a
+/// randomized sweep is reproducible) and handing it to `sample`, which
+/// draws whatever it needs (e.g. an encoder offset or initial position from
+/// a user-specified range) and returns one trial's
+/// `(spec, initial_state, shim)`.
+pub fn randomized_trials<SYS, SHIM>(
+    n: usize,
+    seed: u64,
+    mut sample: impl FnMut(&mut StdRng) -> (SYS::Spec, SYS::State, SHIM),
+) -> Vec<(SYS::Spec, SYS::State, SHIM)>
+where
+    SYS: HarnessAble,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(|_| sample(&mut rng)).collect()
+}
+
+/// Prints a short aggregate report: how many trials were run, the
+/// overall failure rate, and the value range of every numeric field across
+/// the trials that completed.
+pub fn print_report(report: &SweepReport) {
+    println!(
+        "{} trials, {:.1}% failed",
+        report.trials.len(),
+        report.failure_rate() * 100.0
+    );
+
+    let mut fields: Vec<&str> = Vec::new();
+    for t in &report.trials {
+        for (name, _) in &t.stats {
+            if !fields.contains(&name.as_str()) {
+                fields.push(name.as_str());
+            }
+        }
+    }
+    for field in fields {
+        if let Some((lo, hi)) = report.field_extremes(field) {
+            println!("  {:12} range [{:.6}, {:.6}]", field, lo, hi);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SimSpec;
+
+    #[derive(Debug, Clone, Copy)]
+    struct ToySpec;
+
+    impl SimSpec for ToySpec {
+        fn simul_dt(&self) -> si::Second<f64> {
+            1.0 * si::S
+        }
+        fn control_dt(&self) -> si::Second<f64> {
+            1.0 * si::S
+        }
+    }
+
+    struct ToySys;
+
+    #[derive(Debug, Clone, Copy, Serialize)]
+    struct ToyLog {
+        x: f64,
+    }
+
+    impl HarnessAble for ToySys {
+        type State = f64;
+        type ControlResponse = ();
+        type LogData = ToyLog;
+        type Spec = ToySpec;
+        fn sim_time(_spec: &ToySpec, s: f64, _r: (), dur: si::Second<f64>) -> f64 {
+            s + *(dur / si::S)
+        }
+    }
+
+    /// A `StateShim` that panics once its state crosses `fail_above`, so a
+    /// trial can be made to fail deterministically.
+    #[derive(Debug, Clone, Copy)]
+    struct ToyShim {
+        fail_above: f64,
+    }
+
+    impl StateShim<ToySys> for ToyShim {
+        fn update(&mut self, _state: f64) {}
+
+        fn log_dat(&mut self, s: f64, _r: (), _t: si::Second<f64>) -> ToyLog {
+            ToyLog { x: s }
+        }
+
+        fn assert(&mut self, state: f64) {
+            assert!(state <= self.fail_above, "state exceeded threshold");
+        }
+    }
+
+    #[test]
+    fn run_sweep_catches_panics_and_aggregates() {
+        let trials = vec![
+            (ToySpec, 0.0, ToyShim { fail_above: 100.0 }),
+            (ToySpec, 0.0, ToyShim { fail_above: 2.0 }),
+        ];
+        let report = run_sweep::<ToySys, ToyShim>(trials, 5.0 * si::S, None);
+
+        assert_eq!(report.trials.len(), 2);
+        assert!((report.failure_rate() - 0.5).abs() < 1e-12);
+
+        let completed = report
+            .trials
+            .iter()
+            .find(|t| t.failure.is_none())
+            .expect("one trial should have completed");
+        assert!(!completed.stats.is_empty());
+
+        let failed = report
+            .trials
+            .iter()
+            .find(|t| t.failure.is_some())
+            .expect("one trial should have failed");
+        assert_eq!(failed.stats.len(), 0);
+        assert!(failed.failure.as_ref().unwrap().contains("threshold"));
+
+        assert_eq!(report.field_extremes("x"), Some((1.0, 5.0)));
+        assert_eq!(report.field_extremes("nonexistent"), None);
+    }
+}